@@ -1,5 +1,10 @@
 // chat.rs â€” versi lengkap setelah modifikasi fitur "typing indicator" (bubble "...")
 
+use std::collections::{HashMap, HashSet};
+
+use gloo_timers::callback::Timeout;
+use js_sys::Math;
+use pulldown_cmark::{html as md_html, CowStr, Event, Parser as MarkdownParser, Tag};
 use serde::{Deserialize, Serialize};
 use web_sys::HtmlInputElement;
 // use yew::events::InputData; // Removed: InputData is deprecated
@@ -9,6 +14,70 @@ use yew_agent::{Bridge, Bridged};
 use crate::services::event_bus::EventBus;
 use crate::{services::websocket::WebsocketService, User};
 
+/// Berapa lama (ms) sebuah indikator "sedang mengetik" bertahan tanpa frame susulan
+/// sebelum dianggap basi dan dihapus dari `typing_users`.
+const TYPING_TIMEOUT_MS: u32 = 4_000;
+
+/// Interval keep-alive untuk frame typing-start: dikirim ulang secara berkala
+/// selagi `is_typing`, supaya timer auto-evict di sisi remote (`TYPING_TIMEOUT_MS`)
+/// selalu di-re-arm dan tidak pernah habis di tengah pesan yang sedang diketik panjang.
+const TYPING_KEEPALIVE_MS: u32 = TYPING_TIMEOUT_MS / 2;
+
+/// Berapa lama (ms) tanpa aktivitas (mengetik/kirim pesan) sebelum client
+/// mengumumkan dirinya sebagai "away".
+const AWAY_AFTER_MS: u32 = 30_000;
+
+/// Backoff reconnect: mulai dari 0.5s, dobel tiap percobaan, maksimum ~30s.
+const RECONNECT_BASE_MS: u32 = 500;
+const RECONNECT_MAX_MS: u32 = 30_000;
+
+/// Interval heartbeat/ping, supaya koneksi mati terdeteksi sebelum pengguna
+/// mencoba mengirim apa pun.
+const HEARTBEAT_INTERVAL_MS: u32 = 15_000;
+
+/// Ekstensi yang dianggap sebagai gambar dan di-embed langsung lewat `<img>`
+/// alih-alih dirender sebagai Markdown.
+const IMAGE_EXTENSIONS: &[&str] = &[".gif", ".png", ".jpg", ".jpeg", ".webp"];
+
+/// Fast path: apakah seluruh isi pesan (tanpa spasi) adalah URL gambar telanjang?
+fn is_image_url(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    !lower.contains(' ') && IMAGE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Skema URL yang dianggap aman untuk `href`/`src` pada link & gambar hasil
+/// Markdown. Apa pun di luar ini (mis. `javascript:`, `data:`) dinetralkan
+/// supaya tidak bisa dieksekusi saat pesan dirender untuk seluruh room.
+fn is_safe_link_destination(dest: &str) -> bool {
+    let lower = dest.trim().to_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("mailto:")
+}
+
+/// Render isi pesan sebagai Markdown (bold/italic, inline code, fenced code
+/// block, link, bullet list). `pulldown_cmark::html::push_html` sudah meng-escape
+/// `Event::Text`/`Event::Code` sendiri, jadi sumbernya TIDAK di-escape dulu (kalau
+/// dobel, `Vec<String>` di dalam code block akan tampil sebagai `Vec&lt;String&gt;`
+/// alih-alih `Vec<String>`). Markup HTML mentah yang diselundupkan lewat `Event::Html`
+/// diubah menjadi `Event::Text` biasa, sehingga di-escape (sekali) oleh `push_html`
+/// alih-alih dipasang sebagai markup, dan tujuan
+/// link/gambar disaring supaya skema berbahaya (`javascript:`, dll.) tidak lolos ke
+/// HTML yang dipasang lewat `from_html_unchecked` dan disiarkan ke semua peserta room.
+fn render_markdown(message: &str) -> Html {
+    let parser = MarkdownParser::new(message).map(|event| match event {
+        Event::Html(raw) => Event::Text(raw),
+        Event::Start(Tag::Link(link_type, dest, title)) if !is_safe_link_destination(&dest) => {
+            Event::Start(Tag::Link(link_type, CowStr::Borrowed("#"), title))
+        }
+        Event::Start(Tag::Image(link_type, dest, title)) if !is_safe_link_destination(&dest) => {
+            Event::Start(Tag::Image(link_type, CowStr::Borrowed("#"), title))
+        }
+        other => other,
+    });
+    let mut rendered = String::new();
+    md_html::push_html(&mut rendered, parser);
+    Html::from_html_unchecked(AttrValue::from(rendered))
+}
+
 // =====================
 // Messages (Component <-> Runtime)
 // =====================
@@ -20,6 +89,25 @@ pub enum Msg {
     SubmitMessage,
     /// Perubahan pada input chat (mengetik)
     TypingChanged(String),
+    /// Timeout seorang remote user habis tanpa frame susulan -> hapus dari set
+    TypingTimeout(String),
+    /// Keep-alive tick selagi diri sendiri `is_typing` -> kirim ulang frame
+    /// typing-start supaya timer remote di-re-arm
+    TypingKeepalive,
+    /// Tombol "reply" pada sebuah bubble ditekan -> simpan id target balasan
+    ReplyTo(String),
+    /// Batalkan balasan yang sedang disiapkan
+    CancelReply,
+    /// Tidak ada aktivitas (mengetik/kirim) selama beberapa saat -> umumkan away
+    AwayTimeout,
+    /// Backoff reconnect habis -> buka koneksi WebSocket baru
+    ReconnectAttempt,
+    /// Interval heartbeat habis -> kirim frame ping
+    HeartbeatTick,
+    /// Callback socket nyata: koneksi sekarang benar-benar terbuka
+    SocketOpened,
+    /// Callback socket nyata: koneksi ditutup/error -> jadwalkan reconnect
+    SocketClosed,
 }
 
 // =====================
@@ -27,8 +115,89 @@ pub enum Msg {
 // =====================
 #[derive(Deserialize)]
 struct MessageData {
+    /// Id stabil yang di-assign server; dipakai sebagai kunci thread.
+    id: String,
     from: String,
     message: String,
+    /// Pesan yang dibalas, bila ada. `None` berarti root thread.
+    #[serde(default)]
+    parent_id: Option<String>,
+}
+
+/// Payload yang dikirim saat submit pesan; `parent_id` diisi jika user sedang
+/// membalas bubble tertentu.
+#[derive(Serialize)]
+struct OutgoingMessage {
+    message: String,
+    parent_id: Option<String>,
+}
+
+/// Payload untuk `MsgTypes::Typing`: siapa yang mengetik dan apakah dia baru mulai
+/// atau berhenti.
+#[derive(Debug, Deserialize, Serialize)]
+struct TypingData {
+    from: String,
+    is_typing: bool,
+}
+
+/// Payload untuk `MsgTypes::Event`: notifikasi non-chat dari server (user
+/// bergabung/keluar/berganti nama).
+#[derive(Debug, Deserialize)]
+struct SystemEventData {
+    text: String,
+    timestamp: String,
+}
+
+/// Satu baris dalam daftar chat: pesan asli dari user, atau event sistem.
+/// Disatukan dalam satu `Vec` supaya urutan antara chat dan event tetap terjaga.
+#[derive(Debug)]
+enum ChatItem {
+    Message(MessageData),
+    System { text: String, timestamp: String },
+}
+
+/// Status keberadaan (liveness) seorang user, ditampilkan sebagai titik warna
+/// di atas avatar-nya.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum UserStatus {
+    Online,
+    Away,
+    Offline,
+}
+
+impl UserStatus {
+    /// Urutan tampil di sidebar: online lebih dulu.
+    fn sort_rank(self) -> u8 {
+        match self {
+            UserStatus::Online => 0,
+            UserStatus::Away => 1,
+            UserStatus::Offline => 2,
+        }
+    }
+
+    fn dot_class(self) -> &'static str {
+        match self {
+            UserStatus::Online => "bg-green-500",
+            UserStatus::Away => "bg-yellow-500",
+            UserStatus::Offline => "bg-gray-400",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            UserStatus::Online => "Online",
+            UserStatus::Away => "Away",
+            UserStatus::Offline => "Offline",
+        }
+    }
+}
+
+/// Payload untuk frame presence: siapa yang berganti status dan menjadi apa.
+#[derive(Debug, Deserialize, Serialize)]
+struct PresenceData {
+    from: String,
+    status: UserStatus,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -37,6 +206,32 @@ pub enum MsgTypes {
     Users,
     Register,
     Message,
+    Typing,
+    Event,
+    Presence,
+    /// Heartbeat ringan supaya koneksi mati terdeteksi cepat, bukan menggantung.
+    Ping,
+}
+
+/// Status koneksi WebSocket saat ini; direnderkan sebagai banner tipis saat
+/// bukan `Open`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnState {
+    Connecting,
+    Open,
+    Reconnecting,
+    Closed,
+}
+
+impl ConnState {
+    fn banner(self) -> Option<&'static str> {
+        match self {
+            ConnState::Open => None,
+            ConnState::Connecting => Some("Connectingâ€¦"),
+            ConnState::Reconnecting => Some("Connection lost â€” reconnectingâ€¦"),
+            ConnState::Closed => Some("Disconnected"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -54,6 +249,7 @@ struct WebSocketMessage {
 struct UserProfile {
     name: String,
     avatar: String,
+    status: UserStatus,
 }
 
 // =====================
@@ -62,8 +258,34 @@ struct UserProfile {
 pub struct Chat {
     // --- UI state
     users: Vec<UserProfile>,
-    messages: Vec<MessageData>,
+    items: Vec<ChatItem>,
     is_typing: bool,
+    /// Username remote yang sedang mengetik (tidak pernah memuat diri sendiri)
+    typing_users: HashSet<String>,
+    /// Timer per user untuk auto-hapus dari `typing_users` jika tak ada frame susulan
+    typing_timeouts: HashMap<String, Timeout>,
+    /// Timer keep-alive diri sendiri: selagi `is_typing`, mengirim ulang frame
+    /// typing-start sebelum `TYPING_TIMEOUT_MS` di sisi remote habis
+    typing_keepalive: Option<Timeout>,
+    /// Id pesan yang sedang dibalas, bila user menekan "reply" pada sebuah bubble
+    reply_target: Option<String>,
+    /// Apakah diri sendiri sudah mengumumkan status away ke server
+    self_away: bool,
+    /// Timer inactivity yang memicu `Msg::AwayTimeout`; di-arm ulang tiap aktivitas
+    activity_timeout: Option<Timeout>,
+
+    // --- Koneksi
+    conn_state: ConnState,
+    /// Jumlah percobaan reconnect berturut-turut, dipakai untuk hitung backoff
+    reconnect_attempt: u32,
+    /// Pesan `SubmitMessage` yang gagal terkirim saat disconnected, di-flush
+    /// berurutan begitu koneksi `Open` lagi
+    pending_messages: Vec<String>,
+    reconnect_timeout: Option<Timeout>,
+    heartbeat_timeout: Option<Timeout>,
+
+    // --- Identitas
+    username: String,
 
     // --- Refs & services
     chat_input: NodeRef,
@@ -71,6 +293,283 @@ pub struct Chat {
     wss: WebsocketService,                // WebSocket service
 }
 
+impl Chat {
+    /// Buka `WebsocketService` baru, terhubung ke `Msg::SocketOpened`/`Msg::SocketClosed`
+    /// lewat callback `on_open`/`on_close` milik socket itu sendiri. Hanya `on_open`
+    /// yang membuat `conn_state` menjadi `Open`: sukses `tx.try_send(..)` TIDAK
+    /// dipakai sebagai sinyal terbuka, karena `tx` cuma channel buffer ke
+    /// worker socket dan sukses mengirim ke situ tidak berarti socket remote benar-benar
+    /// terbuka. Selain `on_close`, reconnect juga dijadwalkan oleh `flush_pending`
+    /// bila pengiriman antrean lewat `tx` gagal.
+    fn open_socket(ctx: &Context<Self>) -> WebsocketService {
+        let on_open = ctx.link().callback(|()| Msg::SocketOpened);
+        let on_close = ctx.link().callback(|()| Msg::SocketClosed);
+        WebsocketService::new(on_open, on_close)
+    }
+
+    /// Kirim pesan register ke server supaya nama user terdaftar di sisi lain.
+    fn register(&mut self, ctx: &Context<Self>) {
+        let register_msg = WebSocketMessage {
+            message_type: MsgTypes::Register,
+            data: Some(self.username.clone()),
+            data_array: None,
+        };
+        self.ws_send(ctx, serde_json::to_string(&register_msg).unwrap());
+    }
+
+    /// Coba kirim satu frame lewat socket saat ini. Ini murni channel send ke worker
+    /// socket, bukan sinyal koneksi; fungsi ini tidak mengubah `conn_state`.
+    fn ws_send(&mut self, _ctx: &Context<Self>, payload: String) -> bool {
+        self.wss.tx.clone().try_send(payload).is_ok()
+    }
+
+    /// Kirim frame typing-start/typing-stop ke server untuk di-fan-out ke user lain.
+    fn send_typing(&mut self, ctx: &Context<Self>, is_typing: bool) {
+        let typing = TypingData {
+            from: self.username.clone(),
+            is_typing,
+        };
+        let message = WebSocketMessage {
+            message_type: MsgTypes::Typing,
+            data: Some(serde_json::to_string(&typing).unwrap()),
+            data_array: None,
+        };
+        self.ws_send(ctx, serde_json::to_string(&message).unwrap());
+    }
+
+    /// Tandai `username` sedang mengetik dan (re-)arm timer ~4s yang menghapusnya
+    /// jika tidak ada frame susulan.
+    fn arm_typing_timeout(&mut self, ctx: &Context<Self>, username: String) {
+        self.typing_users.insert(username.clone());
+        let link = ctx.link().clone();
+        let timeout_username = username.clone();
+        let timeout = Timeout::new(TYPING_TIMEOUT_MS, move || {
+            link.send_message(Msg::TypingTimeout(timeout_username));
+        });
+        // Menimpa entry lama membuang `Timeout` sebelumnya, yang membatalkannya.
+        self.typing_timeouts.insert(username, timeout);
+    }
+
+    /// (Re-)arm timer keep-alive yang memicu `Msg::TypingKeepalive` selagi diri
+    /// sendiri masih `is_typing`, supaya timer auto-evict remote (`arm_typing_timeout`
+    /// di sisi lain) tidak pernah habis di tengah pesan yang sedang diketik panjang.
+    fn arm_typing_keepalive(&mut self, ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        self.typing_keepalive = Some(Timeout::new(TYPING_KEEPALIVE_MS, move || {
+            link.send_message(Msg::TypingKeepalive);
+        }));
+    }
+
+    /// Kirim frame presence ke server untuk di-fan-out ke user lain.
+    fn send_presence(&mut self, ctx: &Context<Self>, status: UserStatus) {
+        let presence = PresenceData {
+            from: self.username.clone(),
+            status,
+        };
+        let message = WebSocketMessage {
+            message_type: MsgTypes::Presence,
+            data: Some(serde_json::to_string(&presence).unwrap()),
+            data_array: None,
+        };
+        self.ws_send(ctx, serde_json::to_string(&message).unwrap());
+    }
+
+    /// Perbarui status seorang user di sidebar (no-op bila namanya tidak dikenal)
+    /// dan jaga urutan online-dulu. Mengembalikan `true` bila ada perubahan nyata.
+    fn set_user_status(&mut self, name: &str, status: UserStatus) -> bool {
+        let Some(user) = self.users.iter_mut().find(|u| u.name == name) else {
+            return false;
+        };
+        if user.status == status {
+            return false;
+        }
+        user.status = status;
+        self.sort_users();
+        true
+    }
+
+    /// Urutkan sidebar: online dulu, lalu away, lalu offline.
+    fn sort_users(&mut self) {
+        self.users.sort_by_key(|u| u.status.sort_rank());
+    }
+
+    /// (Re-)arm timer inactivity yang memicu `Msg::AwayTimeout` setelah
+    /// `AWAY_AFTER_MS` tanpa aktivitas.
+    fn arm_away_timeout(&mut self, ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        self.activity_timeout = Some(Timeout::new(AWAY_AFTER_MS, move || {
+            link.send_message(Msg::AwayTimeout);
+        }));
+    }
+
+    /// Dipanggil di setiap interaksi pengguna (mengetik, kirim pesan): balik ke
+    /// online bila sebelumnya away, dan reset timer inactivity.
+    fn note_activity(&mut self, ctx: &Context<Self>) {
+        if self.self_away {
+            self.self_away = false;
+            self.send_presence(ctx, UserStatus::Online);
+            self.set_user_status(&self.username.clone(), UserStatus::Online);
+        }
+        self.arm_away_timeout(ctx);
+    }
+
+    /// (Re-)arm heartbeat; tiap tick mengirim frame ping ringan supaya koneksi
+    /// mati terdeteksi sebelum pengguna mencoba mengirim apa pun.
+    fn arm_heartbeat(&mut self, ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        self.heartbeat_timeout = Some(Timeout::new(HEARTBEAT_INTERVAL_MS, move || {
+            link.send_message(Msg::HeartbeatTick);
+        }));
+    }
+
+    /// Jadwalkan percobaan reconnect berikutnya dengan backoff eksponensial
+    /// (0.5s, dobel tiap percobaan, maksimum ~30s) plus jitter acak.
+    fn schedule_reconnect(&mut self, ctx: &Context<Self>) {
+        self.conn_state = ConnState::Reconnecting;
+        let exponent = self.reconnect_attempt.min(10);
+        let backoff = RECONNECT_BASE_MS
+            .saturating_mul(1 << exponent)
+            .min(RECONNECT_MAX_MS);
+        let jitter = (Math::random() * backoff as f64 * 0.3) as u32;
+        self.reconnect_attempt += 1;
+
+        let link = ctx.link().clone();
+        self.reconnect_timeout = Some(Timeout::new(backoff.saturating_add(jitter), move || {
+            link.send_message(Msg::ReconnectAttempt);
+        }));
+    }
+
+    /// Flush pesan yang tertahan selagi disconnected, berurutan. Bila ada yang
+    /// gagal di tengah jalan, sisanya (termasuk yang baru gagal) disimpan lagi
+    /// dan sebuah reconnect baru dijadwalkan.
+    fn flush_pending(&mut self, ctx: &Context<Self>) {
+        let pending = std::mem::take(&mut self.pending_messages);
+        for (i, payload) in pending.iter().enumerate() {
+            if self.wss.tx.clone().try_send(payload.clone()).is_err() {
+                self.pending_messages.extend(pending[i..].iter().cloned());
+                self.schedule_reconnect(ctx);
+                return;
+            }
+        }
+    }
+
+    /// Pesan chat di antara `self.items`, mengabaikan event sistem.
+    fn chat_messages(&self) -> impl Iterator<Item = &MessageData> {
+        self.items.iter().filter_map(|item| match item {
+            ChatItem::Message(m) => Some(m),
+            ChatItem::System { .. } => None,
+        })
+    }
+
+    /// Kelompokkan pesan chat menjadi adjacency list `parent_id -> children`.
+    /// Root ada di entry `None`: pesan tanpa `parent_id`, atau yang `parent_id`-nya
+    /// menunjuk ke pesan yang sudah tidak ada (supaya tetap tampil, bukan hilang).
+    ///
+    /// Sebuah siklus murni (mis. A.parent = B, B.parent = A, keduanya ada) tidak
+    /// pernah masuk entry `None` lewat aturan di atas, jadi di akhir kita
+    /// promosikan pesan yang tidak terjangkau dari root manapun menjadi root
+    /// juga, supaya rantai `parent_id` yang rusak tetap tampil alih-alih hilang
+    /// diam-diam dari daftar.
+    fn thread_children(&self) -> HashMap<Option<String>, Vec<&MessageData>> {
+        let ids: HashSet<&str> = self.chat_messages().map(|m| m.id.as_str()).collect();
+        let mut children: HashMap<Option<String>, Vec<&MessageData>> = HashMap::new();
+        for m in self.chat_messages() {
+            let parent = match &m.parent_id {
+                Some(pid) if ids.contains(pid.as_str()) => Some(pid.clone()),
+                _ => None,
+            };
+            children.entry(parent).or_default().push(m);
+        }
+
+        let mut reachable: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = children
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .map(|m| m.id.as_str())
+            .collect();
+        while let Some(id) = stack.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            if let Some(kids) = children.get(&Some(id.to_string())) {
+                stack.extend(kids.iter().map(|m| m.id.as_str()));
+            }
+        }
+        let orphaned: Vec<&MessageData> = self
+            .chat_messages()
+            .filter(|m| !reachable.contains(m.id.as_str()))
+            .collect();
+        children.entry(None).or_default().extend(orphaned);
+
+        children
+    }
+
+    /// Render satu bubble beserta seluruh balasannya secara rekursif, dengan
+    /// indentasi kiri proporsional terhadap `depth`. `visited` menjaga agar
+    /// `parent_id` yang membentuk siklus tidak membuat rekursi tak berhenti.
+    fn render_thread(
+        &self,
+        ctx: &Context<Self>,
+        children: &HashMap<Option<String>, Vec<&MessageData>>,
+        id: &str,
+        depth: usize,
+        visited: &mut HashSet<String>,
+    ) -> Html {
+        if !visited.insert(id.to_string()) {
+            return Html::default();
+        }
+        let Some(m) = self.chat_messages().find(|m| m.id == id) else {
+            return Html::default();
+        };
+        let user = self
+            .users
+            .iter()
+            .find(|u| u.name == m.from)
+            .cloned()
+            .unwrap_or(UserProfile {
+                name: m.from.clone(),
+                avatar: String::new(),
+                status: UserStatus::Offline,
+            });
+
+        let reply_id = m.id.clone();
+        let onreply = ctx.link().callback(move |_| Msg::ReplyTo(reply_id.clone()));
+        let margin_left = depth * 24;
+
+        let replies = children
+            .get(&Some(id.to_string()))
+            .into_iter()
+            .flatten()
+            .map(|child| self.render_thread(ctx, children, &child.id, depth + 1, visited))
+            .collect::<Vec<_>>();
+
+        html! {
+            <div style={format!("margin-left: {}px;", margin_left)}>
+                <div class="flex items-end w-3/6 bg-gray-100 m-8 rounded-tl-lg rounded-tr-lg rounded-br-lg ">
+                    <img class="w-8 h-8 rounded-full m-3" src={user.avatar.clone()} alt="avatar"/>
+                    <div class="p-3">
+                        <div class="flex text-sm justify-between">
+                            <div>{m.from.clone()}</div>
+                            <button onclick={onreply} class="text-xs text-blue-500 ml-3">{"Reply"}</button>
+                        </div>
+                        <div class="text-xs text-gray-500">
+                            {
+                                if is_image_url(&m.message) {
+                                    html!{<img class="mt-3" src={m.message.clone()} />}
+                                } else {
+                                    render_markdown(&m.message)
+                                }
+                            }
+                        </div>
+                    </div>
+                </div>
+                { for replies }
+            </div>
+        }
+    }
+}
+
 impl Component for Chat {
     type Message = Msg;
     type Properties = ();
@@ -83,32 +582,38 @@ impl Component for Chat {
             .context::<User>(Callback::noop())
             .expect("context to be set");
 
-        let wss = WebsocketService::new();
+        let wss = Self::open_socket(ctx);
         let username = user.username.borrow().clone();
 
-        // Kirim pesan register ke server
-        let register_msg = WebSocketMessage {
-            message_type: MsgTypes::Register,
-            data: Some(username.clone()),
-            data_array: None,
-        };
-        let _ = wss
-            .tx
-            .clone()
-            .try_send(serde_json::to_string(&register_msg).unwrap());
-
-        Self {
+        let mut this = Self {
             users: vec![],
-            messages: vec![],
+            items: vec![],
             is_typing: false,
+            typing_users: HashSet::new(),
+            typing_timeouts: HashMap::new(),
+            typing_keepalive: None,
+            reply_target: None,
+            self_away: false,
+            activity_timeout: None,
+            // Belum tahu socket sudah benar-benar terbuka atau belum; menunggu
+            // `Msg::SocketOpened`/`Msg::SocketClosed` dari socket itu sendiri.
+            conn_state: ConnState::Connecting,
+            reconnect_attempt: 0,
+            pending_messages: vec![],
+            reconnect_timeout: None,
+            heartbeat_timeout: None,
+            username,
             chat_input: NodeRef::default(),
             wss,
             _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
-        }
+        };
+        this.arm_away_timeout(ctx);
+        this.arm_heartbeat(ctx);
+        this
     }
 
     // ---------- update ----------
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             // ------------
             // EventBus / WebSocket message
@@ -117,57 +622,154 @@ impl Component for Chat {
                 let ws_msg: WebSocketMessage = serde_json::from_str(&raw).unwrap();
                 match ws_msg.message_type {
                     MsgTypes::Users => {
-                        // Perbarui daftar user
+                        // Perbarui daftar user, pertahankan status presence yang sudah diketahui
                         let users_from_message = ws_msg.data_array.unwrap_or_default();
                         self.users = users_from_message
                             .iter()
-                            .map(|u| UserProfile {
-                                name: u.into(),
-                                avatar: format!(
-                                    "https://avatars.dicebear.com/api/adventurer-neutral/{}.svg",
-                                    u
-                                ),
+                            .map(|u| {
+                                let status = self
+                                    .users
+                                    .iter()
+                                    .find(|existing| &existing.name == u)
+                                    .map(|existing| existing.status)
+                                    .unwrap_or(UserStatus::Online);
+                                UserProfile {
+                                    name: u.into(),
+                                    avatar: format!(
+                                        "https://avatars.dicebear.com/api/adventurer-neutral/{}.svg",
+                                        u
+                                    ),
+                                    status,
+                                }
                             })
                             .collect();
+                        self.sort_users();
                         true
                     }
                     MsgTypes::Message => {
                         // Tambahkan pesan baru
                         if let Some(data) = ws_msg.data {
                             if let Ok(message_data) = serde_json::from_str::<MessageData>(&data) {
-                                self.messages.push(message_data);
+                                self.items.push(ChatItem::Message(message_data));
+                            }
+                        }
+                        true
+                    }
+                    MsgTypes::Event => {
+                        // Notifikasi sistem (join/leave/rename), tampil inline di antara chat
+                        if let Some(data) = ws_msg.data {
+                            if let Ok(event) = serde_json::from_str::<SystemEventData>(&data) {
+                                self.items.push(ChatItem::System {
+                                    text: event.text,
+                                    timestamp: event.timestamp,
+                                });
                             }
                         }
                         true
                     }
+                    MsgTypes::Typing => {
+                        // Presence signal dari user lain; abaikan frame dari diri sendiri
+                        if let Some(data) = ws_msg.data {
+                            if let Ok(typing) = serde_json::from_str::<TypingData>(&data) {
+                                if typing.from != self.username {
+                                    if typing.is_typing {
+                                        self.arm_typing_timeout(ctx, typing.from);
+                                    } else {
+                                        self.typing_users.remove(&typing.from);
+                                        self.typing_timeouts.remove(&typing.from);
+                                    }
+                                    return true;
+                                }
+                            }
+                        }
+                        false
+                    }
+                    MsgTypes::Presence => {
+                        // User lain berganti status online/away/offline
+                        if let Some(data) = ws_msg.data {
+                            if let Ok(presence) = serde_json::from_str::<PresenceData>(&data) {
+                                if presence.from != self.username {
+                                    return self.set_user_status(&presence.from, presence.status);
+                                }
+                            }
+                        }
+                        false
+                    }
                     _ => false,
                 }
             }
             // ------------
+            // Timeout indikator mengetik seorang remote user habis
+            // ------------
+            Msg::TypingTimeout(username) => {
+                self.typing_timeouts.remove(&username);
+                self.typing_users.remove(&username)
+            }
+            // ------------
+            // Keep-alive tick: selagi masih mengetik, kirim ulang frame typing-start
+            // supaya timer auto-evict di sisi remote tidak habis di tengah pesan
+            // ------------
+            Msg::TypingKeepalive => {
+                if self.is_typing {
+                    self.send_typing(ctx, true);
+                    self.arm_typing_keepalive(ctx);
+                }
+                false
+            }
+            // ------------
+            // Tidak ada aktivitas selama AWAY_AFTER_MS -> umumkan diri sebagai away
+            // ------------
+            Msg::AwayTimeout => {
+                self.self_away = true;
+                self.send_presence(ctx, UserStatus::Away);
+                self.set_user_status(&self.username.clone(), UserStatus::Away)
+            }
+            // ------------
+            // Tombol "reply" pada sebuah bubble ditekan
+            // ------------
+            Msg::ReplyTo(id) => {
+                self.reply_target = Some(id);
+                true
+            }
+            Msg::CancelReply => self.reply_target.take().is_some(),
+            // ------------
             // Tombol kirim ditekan
             // ------------
             Msg::SubmitMessage => {
+                self.note_activity(ctx);
                 if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
                     let value = input.value();
                     if value.trim().is_empty() {
                         return false; // abaikan pesan kosong
                     }
 
-                    // Kirim ke WebSocket
+                    // Kirim ke WebSocket, sertakan parent_id bila sedang membalas
+                    let outgoing = OutgoingMessage {
+                        message: value,
+                        parent_id: self.reply_target.take(),
+                    };
                     let message = WebSocketMessage {
                         message_type: MsgTypes::Message,
-                        data: Some(value),
+                        data: Some(serde_json::to_string(&outgoing).unwrap()),
                         data_array: None,
                     };
-                    let _ = self
-                        .wss
-                        .tx
-                        .clone()
-                        .try_send(serde_json::to_string(&message).unwrap());
+                    let payload = serde_json::to_string(&message).unwrap();
+
+                    // Bila koneksi sedang down, jangan coba kirim dulu; antre
+                    // saja supaya urutannya tetap benar saat di-flush nanti.
+                    let delivered =
+                        self.conn_state == ConnState::Open && self.ws_send(ctx, payload.clone());
+                    if !delivered {
+                        self.pending_messages.push(payload);
+                    }
 
                     // Kosongkan input & reset indikator
                     input.set_value("");
-                    self.is_typing = false;
+                    if self.is_typing {
+                        self.is_typing = false;
+                        self.send_typing(ctx, false);
+                        self.typing_keepalive = None;
+                    }
                 }
                 true
             }
@@ -175,17 +777,73 @@ impl Component for Chat {
             // Perubahan teks input (typing)
             // ------------
             Msg::TypingChanged(val) => {
+                self.note_activity(ctx);
                 let currently_typing = !val.trim().is_empty();
                 if self.is_typing != currently_typing {
                     self.is_typing = currently_typing;
+                    self.send_typing(ctx, currently_typing);
+                    if currently_typing {
+                        self.arm_typing_keepalive(ctx);
+                    } else {
+                        self.typing_keepalive = None;
+                    }
                     true // rerender hanya jika status berubah
                 } else {
                     false
                 }
             }
+            // ------------
+            // Backoff reconnect habis: buka koneksi WebSocket baru dan tunggu
+            // `Msg::SocketOpened`/`Msg::SocketClosed` dari socket itu sendiri
+            // ------------
+            Msg::ReconnectAttempt => {
+                self.wss = Self::open_socket(ctx);
+                self.conn_state = ConnState::Connecting;
+                true
+            }
+            // ------------
+            // Interval heartbeat habis: kirim ping supaya koneksi mati terdeteksi cepat
+            // ------------
+            Msg::HeartbeatTick => {
+                let ping = WebSocketMessage {
+                    message_type: MsgTypes::Ping,
+                    data: None,
+                    data_array: None,
+                };
+                self.ws_send(ctx, serde_json::to_string(&ping).unwrap());
+                self.arm_heartbeat(ctx);
+                false
+            }
+            // ------------
+            // Socket nyata baru saja terbuka: daftarkan username, reset backoff,
+            // lalu flush pesan yang tertahan selagi disconnected
+            // ------------
+            Msg::SocketOpened => {
+                self.conn_state = ConnState::Open;
+                self.reconnect_attempt = 0;
+                self.register(ctx);
+                self.flush_pending(ctx);
+                true
+            }
+            // ------------
+            // Socket nyata ditutup/error: jadwalkan reconnect, kecuali sudah dijadwalkan
+            // ------------
+            Msg::SocketClosed => {
+                if self.conn_state != ConnState::Reconnecting {
+                    self.schedule_reconnect(ctx);
+                }
+                true
+            }
         }
     }
 
+    // ---------- destroy ----------
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        // Timer reconnect/heartbeat/typing otomatis batal lewat Drop; tandai
+        // koneksi sebagai ditutup sehingga status akhirnya konsisten.
+        self.conn_state = ConnState::Closed;
+    }
+
     // ---------- view ----------
     fn view(&self, ctx: &Context<Self>) -> Html {
         // Callbacks
@@ -204,12 +862,15 @@ impl Component for Chat {
                     <div class="text-xl p-3">{"Users"}</div>
                     { for self.users.iter().map(|u| html!{
                         <div class="flex m-3 bg-white rounded-lg p-2">
-                            <img class="w-12 h-12 rounded-full" src={u.avatar.clone()} alt="avatar"/>
+                            <div class="relative w-12 h-12">
+                                <img class="w-12 h-12 rounded-full" src={u.avatar.clone()} alt="avatar"/>
+                                <span class={format!("absolute bottom-0 right-0 w-3 h-3 rounded-full border-2 border-white {}", u.status.dot_class())}></span>
+                            </div>
                             <div class="flex-grow p-3">
                                 <div class="flex text-xs justify-between">
                                     <div>{u.name.clone()}</div>
                                 </div>
-                                <div class="text-xs text-gray-400">{"Hi there!"}</div>
+                                <div class="text-xs text-gray-400">{u.status.label()}</div>
                             </div>
                         </div>
                     }) }
@@ -222,40 +883,85 @@ impl Component for Chat {
                         <div class="text-xl p-3">{"ðŸ’¬ Chat!"}</div>
                     </div>
 
+                    // ----- Banner status koneksi -----
+                    {
+                        if let Some(text) = self.conn_state.banner() {
+                            html! {
+                                <div class="w-full text-center text-xs py-1 bg-yellow-100 text-yellow-800">
+                                    {text}
+                                </div>
+                            }
+                        } else {
+                            Html::default()
+                        }
+                    }
+
                     // ----- Messages list -----
                     <div class="w-full grow overflow-auto border-b-2 border-gray-300">
-                        // Bubble "..." â€“ tampil saat mengetik
+                        // Bubble indikator mengetik milik user lain (real presence signal,
+                        // bukan cuma state lokal)
                         {
-                            if self.is_typing {
-                                html! {<div class="flex items-end w-3/6 bg-gray-200 m-8 rounded-tl-lg rounded-tr-lg rounded-br-lg p-3 italic text-gray-600">{"..."}</div>}
+                            if !self.typing_users.is_empty() {
+                                let mut names: Vec<&str> =
+                                    self.typing_users.iter().map(String::as_str).collect();
+                                names.sort_unstable();
+                                let verb = if names.len() == 1 { "is" } else { "are" };
+                                html! {
+                                    <div class="flex items-end w-3/6 bg-gray-200 m-8 rounded-tl-lg rounded-tr-lg rounded-br-lg p-3 italic text-gray-600">
+                                        {format!("{} {} typingâ€¦", names.join(", "), verb)}
+                                    </div>
+                                }
                             } else {
                                 Html::default()
                             }
                         }
 
-                        { for self.messages.iter().map(|m| {
-                            let user = self.users.iter().find(|u| u.name == m.from)
-                                .cloned()
-                                .unwrap_or(UserProfile { name: m.from.clone(), avatar: String::new() });
-                            html!{
-                                <div class="flex items-end w-3/6 bg-gray-100 m-8 rounded-tl-lg rounded-tr-lg rounded-br-lg ">
-                                    <img class="w-8 h-8 rounded-full m-3" src={user.avatar.clone()} alt="avatar"/>
-                                    <div class="p-3">
-                                        <div class="text-sm">{m.from.clone()}</div>
-                                        <div class="text-xs text-gray-500">
-                                            {
-                                                if m.message.ends_with(".gif") {
-                                                    html!{<img class="mt-3" src={m.message.clone()} />}
-                                                } else {
-                                                    html!{m.message.clone()}
-                                                }
-                                            }
+                        {
+                            // Iterasi `items` sesuai urutan kedatangan supaya chat & event
+                            // sistem tetap tersisip di posisi aslinya. Pesan root dirender
+                            // lewat `render_thread` (yang juga merender balasannya secara
+                            // rekursif); balasan sendiri dilewati di sini agar tidak dobel.
+                            let children = self.thread_children();
+                            let root_ids: HashSet<&str> = children
+                                .get(&None)
+                                .into_iter()
+                                .flatten()
+                                .map(|m| m.id.as_str())
+                                .collect();
+                            let mut visited: HashSet<String> = HashSet::new();
+                            let rows: Vec<Html> = self
+                                .items
+                                .iter()
+                                .map(|item| match item {
+                                    ChatItem::Message(m) if root_ids.contains(m.id.as_str()) => {
+                                        self.render_thread(ctx, &children, &m.id, 0, &mut visited)
+                                    }
+                                    ChatItem::Message(_) => Html::default(),
+                                    ChatItem::System { text, timestamp } => html! {
+                                        <div class="text-center text-xs italic text-gray-400 my-2">
+                                            {format!("{} Â· {}", text, timestamp)}
                                         </div>
-                                    </div>
+                                    },
+                                })
+                                .collect();
+                            html! { <>{ for rows }</> }
+                        }
+                    </div>
+
+                    // ----- Bar "sedang membalas" -----
+                    {
+                        if let Some(target) = &self.reply_target {
+                            let cancel = ctx.link().callback(|_| Msg::CancelReply);
+                            html! {
+                                <div class="w-full px-3 py-1 text-xs bg-gray-100 text-gray-600 flex justify-between">
+                                    <span>{format!("Replying to #{}", target)}</span>
+                                    <button onclick={cancel} class="text-blue-500">{"Cancel"}</button>
                                 </div>
                             }
-                        }) }
-                    </div>
+                        } else {
+                            Html::default()
+                        }
+                    }
 
                     // ----- Input bar -----
                     <div class="w-full h-14 flex px-3 items-center">